@@ -0,0 +1,43 @@
+//! Benchmarks for the buffered `BitWriter`/`BitReader` internals.
+//!
+//! These measure the wall-clock effect of batching bytes into a wide internal buffer instead of
+//! calling the inner reader/writer once per byte. The corresponding reduction in inner-writer
+//! `write_all`/inner-reader `read` call counts is asserted directly by
+//! `test_writer_batches_inner_write_all_calls`/`test_reader_batches_inner_read_calls` in
+//! `src/lib.rs`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::io::Cursor;
+
+use bitstream::{BitReader, BitWriter};
+
+const BIT_COUNT: usize = 1_000_000;
+
+fn write_bits(c: &mut Criterion) {
+    c.bench_function("write 1M bits", |b| {
+        b.iter(|| {
+            let mut out = Vec::new();
+            let mut writer = BitWriter::new(&mut out);
+            for _ in 0..BIT_COUNT / 8 {
+                writer.write_bits(8, black_box(0xAB)).unwrap();
+            }
+            writer.finish().unwrap();
+        })
+    });
+}
+
+fn read_bits(c: &mut Criterion) {
+    let data = vec![0xABu8; BIT_COUNT / 8];
+
+    c.bench_function("read 1M bits", |b| {
+        b.iter(|| {
+            let mut reader = BitReader::new(Cursor::new(&data));
+            for _ in 0..BIT_COUNT / 8 {
+                black_box(reader.read_bits(8).unwrap().unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, write_bits, read_bits);
+criterion_main!(benches);
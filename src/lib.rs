@@ -5,17 +5,34 @@
 //! implementation.
 
 use std::io::{Write, Read};
+use std::io::{Error, ErrorKind};
 use std::io::Result as IOResult;
+use std::marker::PhantomData;
 
 pub mod padding;
 pub use padding::{Padding, NoPadding, LengthPadding};
 
+pub mod order;
+pub use order::{BitOrder, Msb, Lsb};
+
+pub mod store;
+pub use store::BitStore;
+
+/// Size of the internal buffer used by [BitWriter](struct.BitWriter.html) to batch up complete
+/// bytes before handing them to the inner writer in a single `write_all` call, and by
+/// [BitReader](struct.BitReader.html) to batch up reads from the inner reader in a single `read`
+/// call.
+const BUFFER_SIZE: usize = 4096;
+
 /// **BitWriter** is a writer for single bit values
 ///
 /// Bits will be grouped to a single byte before writing to the inner writer.
-/// The first Bit will be the most significant bit of the byte.
+/// The first Bit will be the most significant bit of the byte, unless configured otherwise
+/// through the `O` type parameter. See [BitOrder](order/trait.BitOrder.html).
 ///
-/// When dropping this writer, any remaining bits will be written according to the padding used.
+/// When dropping this writer, any remaining bits will be written according to the padding used,
+/// ignoring any I/O error that might occur. Use [finish](#method.finish) instead to pad, flush
+/// and get the inner writer back while being able to observe such an error.
 /// The default padding is [NoPadding](struct.NoPadding.html)
 ///
 /// # Examples
@@ -29,11 +46,14 @@ pub use padding::{Padding, NoPadding, LengthPadding};
 /// assert!(bit_writer.write_bit(true).is_ok());
 /// assert!(bit_writer.write_bit(false).is_ok());
 /// ```
-pub struct BitWriter<W, P> where W: Write, P: Padding {
-    inner: W,
+pub struct BitWriter<W, P, O = Msb> where W: Write, P: Padding, O: BitOrder {
+    inner: Option<W>,
     padder: P,
     last_byte: u8,
     last_fill: u8,
+    buffer: Box<[u8]>,
+    buffer_fill: usize,
+    _order: PhantomData<O>,
 }
 
 
@@ -55,70 +75,227 @@ pub struct BitWriter<W, P> where W: Write, P: Padding {
 /// assert!(option.is_some());
 /// assert!(option.unwrap());
 /// ```
-pub struct BitReader<R, P> where R: Read, P: Padding {
+pub struct BitReader<R, P, O = Msb> where R: Read, P: Padding, O: BitOrder {
     padder: P,
     inner: R,
     ended: bool,
-    fill: usize,
-    current: u8,
     buffer: Box<[u8]>,
-    bits_left: usize,
+    filled: usize,
+    byte_pos: usize,
+    bit_pos: u8,
+    final_bits_left: Option<usize>,
+    _order: PhantomData<O>,
 }
 
 
-impl<W> BitWriter<W, NoPadding> where W: Write {
+impl<W> BitWriter<W, NoPadding, Msb> where W: Write {
     /// Create a new BitWriter with no padding, writing to the inner writer.
     pub fn new(write: W) -> Self {
         BitWriter::with_padding(write, NoPadding::new())
     }
 }
 
-impl<W, P> BitWriter<W, P> where W: Write, P: Padding {
+impl<W, P> BitWriter<W, P, Msb> where W: Write, P: Padding {
     /// Create a new BitWriter with the given padding
     pub fn with_padding(write: W, padder: P) -> Self {
+        BitWriter::with_padding_and_order(write, padder)
+    }
+}
+
+impl<W, P, O> BitWriter<W, P, O> where W: Write, P: Padding, O: BitOrder {
+    /// Create a new BitWriter with the given padding and bit order.
+    ///
+    /// Use this instead of [with_padding](#method.with_padding) to select a bit order other than
+    /// the default [Msb](order/struct.Msb.html), e.g.
+    /// `BitWriter::<_, _, Lsb>::with_padding_and_order(write, padder)`.
+    pub fn with_padding_and_order(write: W, padder: P) -> Self {
         BitWriter {
-            inner: write,
+            inner: Some(write),
             padder: padder,
             last_byte: 0,
             last_fill: 0,
+            buffer: vec![0; BUFFER_SIZE].into_boxed_slice(),
+            buffer_fill: 0,
+            _order: PhantomData,
         }
     }
 
+    /// Write a value implementing [BitStore](store/trait.BitStore.html) to the inner writer.
+    ///
+    /// # Failures
+    /// Returns an error if the inner writer returns an error
+    pub fn write_value<T: BitStore>(&mut self, value: &T) -> IOResult<()> {
+        value.write_to(self)
+    }
+
     /// Write a single bit to the inner writer.
     ///
+    /// Completed bytes are accumulated into an internal buffer and only handed to the inner
+    /// writer in bulk, see [flush](#method.flush).
+    ///
     /// # Failures
     /// Returns an error if the inner writer returns an error
     pub fn write_bit(&mut self, bit: bool) -> IOResult<()> {
         if bit {
-            let data = 128u8 >> self.last_fill;
-            self.last_byte |= data;
+            self.last_byte |= O::mask(self.last_fill);
         }
 
         self.last_fill += 1;
         if self.last_fill == 8 {
-            self.inner.write_all(&[self.last_byte])?;
+            let byte = self.last_byte;
             self.last_byte = 0;
-            self.last_fill = 0
+            self.last_fill = 0;
+            self.push_byte(byte)?;
         }
         Ok(())
     }
+
+    /// Write the low `bit_width` bits of `value` to the inner writer, feeding them in from the
+    /// most significant down to the least significant.
+    ///
+    /// This is equivalent to calling [write_bit](#method.write_bit) `bit_width` times with the
+    /// individual bits of `value`, but packs whole bytes at a time through a scratch
+    /// accumulator instead of paying a function call per bit.
+    ///
+    /// # Panics
+    /// Panics if `bit_width` is greater than 64.
+    ///
+    /// # Failures
+    /// Returns an error if the inner writer returns an error
+    pub fn write_bits(&mut self, bit_width: u8, value: u64) -> IOResult<()> {
+        assert!(bit_width <= 64, "bit_width must not be greater than 64");
+        if bit_width == 0 {
+            return Ok(());
+        }
+
+        let value_masked = if bit_width == 64 {
+            value
+        } else {
+            value & ((1u64 << bit_width) - 1)
+        };
+
+        // `pending` is the last_fill bits still waiting in `last_byte`, translated into the
+        // canonical MSB-first layout and right-justified, so it can be concatenated with
+        // `value_masked` to form one combined, order-agnostic bit stream.
+        let pending = if self.last_fill == 0 {
+            0
+        } else {
+            (O::reorder(self.last_byte) >> (8 - self.last_fill)) as u128
+        };
+        let total_bits = self.last_fill as u32 + bit_width as u32;
+        let combined = (pending << bit_width) | value_masked as u128;
+
+        let full_bytes = total_bits / 8;
+        for i in 0..full_bytes {
+            let shift = total_bits - 8 * (i + 1);
+            let canonical = ((combined >> shift) & 0xFF) as u8;
+            self.push_byte(O::reorder(canonical))?;
+        }
+
+        let remainder = total_bits % 8;
+        if remainder > 0 {
+            let bits = (combined & ((1u128 << remainder) - 1)) as u8;
+            self.last_byte = O::reorder(bits << (8 - remainder));
+            self.last_fill = remainder as u8;
+        } else {
+            self.last_byte = 0;
+            self.last_fill = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Push a completed, physically-packed byte into the internal block buffer, flushing it in
+    /// bulk to the inner writer once full.
+    fn push_byte(&mut self, byte: u8) -> IOResult<()> {
+        self.buffer[self.buffer_fill] = byte;
+        self.buffer_fill += 1;
+        if self.buffer_fill == self.buffer.len() {
+            self.flush_buffer()?;
+        }
+        Ok(())
+    }
+
+    /// Flush any complete buffered bytes and the inner writer, without finalizing padding.
+    ///
+    /// Unlike [finish](#method.finish), the writer can still be used afterwards.
+    ///
+    /// # Failures
+    /// Returns an error if the inner writer returns an error
+    pub fn flush(&mut self) -> IOResult<()> {
+        self.flush_buffer()?;
+        self.inner().flush()
+    }
+
+    /// Pad and flush the remaining bits, then return the inner writer.
+    ///
+    /// Unlike the `Drop` implementation, this allows inspecting the result of the final padding
+    /// write.
+    ///
+    /// # Failures
+    /// Returns an error if the padding or the inner writer returns an error
+    pub fn finish(mut self) -> IOResult<W> {
+        self.flush_buffer()?;
+        let mut inner = self.inner.take().expect("inner writer already taken");
+        self.padder.pad(self.last_byte, self.last_fill, &mut inner)?;
+        Ok(inner)
+    }
+
+    /// Get a reference to the inner writer.
+    pub fn as_inner_ref(&self) -> &W {
+        self.inner.as_ref().expect("inner writer already taken")
+    }
+
+    /// Get a mutable reference to the inner writer.
+    ///
+    /// Writing directly to the inner writer may corrupt the bit stream, use with care.
+    pub fn as_inner_mut(&mut self) -> &mut W {
+        self.inner()
+    }
+
+    /// Write out any complete bytes accumulated in the internal buffer with a single `write_all`
+    /// call.
+    fn flush_buffer(&mut self) -> IOResult<()> {
+        if self.buffer_fill > 0 {
+            let buffer_fill = self.buffer_fill;
+            // Mark the buffer as drained before handing it to the inner writer: `write_all`
+            // makes no guarantee about how many bytes actually landed if it errors, so there is
+            // no reliable partial-write count to keep around. Clearing eagerly means a failed
+            // flush can never leave `buffer_fill` pointing past the end of `buffer` (which would
+            // panic on the next `push_byte`), and that `finish`/`Drop` never resend bytes that
+            // were already handed to the inner writer once.
+            self.buffer_fill = 0;
+            let inner = self.inner.as_mut().expect("inner writer already taken");
+            inner.write_all(&self.buffer[..buffer_fill])?;
+        }
+        Ok(())
+    }
+
+    fn inner(&mut self) -> &mut W {
+        self.inner.as_mut().expect("inner writer already taken")
+    }
 }
 
-impl<W, P> Drop for BitWriter<W, P> where W: Write, P: Padding {
+impl<W, P, O> Drop for BitWriter<W, P, O> where W: Write, P: Padding, O: BitOrder {
     fn drop(&mut self) {
-        let _ = self.padder.pad(self.last_byte, self.last_fill, &mut self.inner);
+        if let Some(mut inner) = self.inner.take() {
+            if self.buffer_fill > 0 {
+                let _ = inner.write_all(&self.buffer[..self.buffer_fill]);
+            }
+            let _ = self.padder.pad(self.last_byte, self.last_fill, &mut inner);
+        }
     }
 }
 
 
-impl<R> BitReader<R, NoPadding> where R: Read {
+impl<R> BitReader<R, NoPadding, Msb> where R: Read {
     /// Create a new BitReader, with no padding, reading from the inner reader.
     pub fn new(reader: R) -> Self {
         BitReader::with_padding(reader, NoPadding::new())
     }
 }
 
-impl<R, P> BitReader<R, P> where R: Read, P: Padding {
+impl<R, P> BitReader<R, P, Msb> where R: Read, P: Padding {
 
     /// Create a new BitReader, using the supplied padding.
     ///
@@ -140,42 +317,77 @@ impl<R, P> BitReader<R, P> where R: Read, P: Padding {
     /// assert!(last.unwrap().is_none());
     /// ```
     pub fn with_padding(reader: R, padder: P) -> Self {
-        let buf_size = padder.max_size() + 1;
+        BitReader::with_padding_and_order(reader, padder)
+    }
+}
+
+impl<R, P, O> BitReader<R, P, O> where R: Read, P: Padding, O: BitOrder {
+
+    /// Create a new BitReader, using the supplied padding and bit order.
+    ///
+    /// Use this instead of [with_padding](#method.with_padding) to select a bit order other than
+    /// the default [Msb](order/struct.Msb.html), e.g.
+    /// `BitReader::<_, _, Lsb>::with_padding_and_order(reader, padder)`.
+    pub fn with_padding_and_order(reader: R, padder: P) -> Self {
+        let buf_size = padder.max_size() + BUFFER_SIZE;
         let buffer = vec![0; buf_size];
 
         BitReader {
             inner: reader,
             padder: padder,
-            fill: 0,
-            ended: false,
             buffer: buffer.into_boxed_slice(),
-            current: 0,
-            bits_left: 0,
+            filled: 0,
+            byte_pos: 0,
+            bit_pos: 0,
+            ended: false,
+            final_bits_left: None,
+            _order: PhantomData,
         }
     }
 
+    /// Read a value implementing [BitStore](store/trait.BitStore.html) from the inner reader.
+    ///
+    /// # Failures
+    /// Will return an error if the inner reader returns one, or if the stream ends in the middle
+    /// of the value
+    pub fn read_value<T: BitStore>(&mut self) -> IOResult<Option<T>> {
+        T::read_from(self)
+    }
+
+    /// Make sure at least `max_size() + 1` bytes are buffered ahead of `byte_pos`, or that the
+    /// inner reader has been drained entirely.
+    ///
+    /// Keeping that much of a look-ahead means a byte can only be reached once we are certain
+    /// whether it falls inside the padding's trailing window, without having to re-read the
+    /// inner reader one byte at a time.
     fn fill_buffer(&mut self) -> IOResult<()> {
-        while !self.ended && self.fill != self.buffer.len() {
-            match self.inner.read(&mut self.buffer[self.fill..]) {
+        let max_size = self.padder.max_size();
+        while !self.ended && self.filled - self.byte_pos <= max_size {
+            if self.byte_pos > 0 {
+                self.buffer.copy_within(self.byte_pos..self.filled, 0);
+                self.filled -= self.byte_pos;
+                self.byte_pos = 0;
+            }
+
+            match self.inner.read(&mut self.buffer[self.filled..]) {
                 Ok(0) => {
                     self.ended = true;
-                    let buf_pad_start = if self.fill < self.buffer.len() {
-                        0
-                    } else {
-                        1
-                    };
-                    self.bits_left = self.padder.bits_left(&self.buffer[buf_pad_start..self.fill])?;
-                }
-                Ok(n) => {
-                    self.fill += n;
-                    self.bits_left = 8;
+                    let tail_start = self.filled.saturating_sub(max_size);
+                    self.final_bits_left = Some(self.padder.bits_left(&self.buffer[tail_start..self.filled])?);
                 }
+                Ok(n) => self.filled += n,
                 Err(e) => return Err(e),
             }
         }
         Ok(())
     }
 
+    /// Get the index of the first byte that belongs to the padding's trailing window, once the
+    /// inner reader is known to be fully drained.
+    fn tail_start(&self) -> usize {
+        self.filled.saturating_sub(self.padder.max_size())
+    }
+
     /// Read a single bit.
     ///
     /// End of stream is signaled by returning  `Ok(None)`
@@ -184,26 +396,93 @@ impl<R, P> BitReader<R, P> where R: Read, P: Padding {
     /// Will return an error if the inner reader returns one
     pub fn read_bit(&mut self) -> IOResult<Option<bool>> {
         self.fill_buffer()?;
-        if self.bits_left == 0 {
-            Ok(None)
-        } else {
-            let res = (self.buffer[0] & (128u8 >> self.current)) == (128u8 >> self.current);
-            self.current += 1;
-            self.bits_left -= 1;
-
-            if self.current == 8 {
-                self.current = 0;
-                self.fill -= 1;
-                unsafe {
-                    std::ptr::copy(self.buffer[1..].as_ptr(), self.buffer[..].as_mut_ptr(), self.buffer.len() - 1);
+
+        if self.ended && self.byte_pos >= self.tail_start() {
+            let bits_left = self.final_bits_left.unwrap_or(0);
+            if bits_left == 0 {
+                return Ok(None);
+            }
+
+            let mask = O::mask(self.bit_pos);
+            let res = (self.buffer[self.byte_pos] & mask) == mask;
+            self.bit_pos += 1;
+            self.final_bits_left = Some(bits_left - 1);
+            if self.bit_pos == 8 {
+                self.bit_pos = 0;
+                self.byte_pos += 1;
+            }
+            return Ok(Some(res));
+        }
+
+        let mask = O::mask(self.bit_pos);
+        let res = (self.buffer[self.byte_pos] & mask) == mask;
+        self.bit_pos += 1;
+        if self.bit_pos == 8 {
+            self.bit_pos = 0;
+            self.byte_pos += 1;
+        }
+        Ok(Some(res))
+    }
+
+    /// Read `bit_width` bits, most significant bit first, returning them as a `u64`.
+    ///
+    /// End of stream is signaled by returning `Ok(None)`, but only if there are no bits left to
+    /// read at all. If the stream ends after only some of the requested bits could be read, an
+    /// error of kind [InvalidData](https://doc.rust-lang.org/std/io/enum.ErrorKind.html) is
+    /// returned instead.
+    ///
+    /// Whenever the cursor is byte-aligned, at least 8 bits are still needed and the cursor is not
+    /// yet inside the padding's trailing bytes, whole bytes are pulled out of the internal buffer
+    /// at once (translated from this reader's `O` packing into the canonical layout) instead of
+    /// going through [read_bit](#method.read_bit) bit by bit. The trailing bytes are read bit by
+    /// bit.
+    ///
+    /// # Panics
+    /// Panics if `bit_width` is greater than 64.
+    ///
+    /// # Failures
+    /// Will return an error if the inner reader returns one, or if the stream ends before
+    /// `bit_width` bits could be read.
+    pub fn read_bits(&mut self, bit_width: u8) -> IOResult<Option<u64>> {
+        assert!(bit_width <= 64, "bit_width must not be greater than 64");
+        if bit_width == 0 {
+            return Ok(Some(0));
+        }
+
+        let mut value = 0u64;
+        let mut read = 0u8;
+        while read < bit_width {
+            if bit_width - read >= 8 && self.bit_pos == 0 {
+                self.fill_buffer()?;
+                let in_tail = self.ended && self.byte_pos >= self.tail_start();
+                if !in_tail && self.byte_pos < self.filled {
+                    let canonical = O::reorder(self.buffer[self.byte_pos]);
+                    value = (value << 8) | canonical as u64;
+                    read += 8;
+                    self.byte_pos += 1;
+                    continue;
+                }
+            }
+
+            match self.read_bit()? {
+                Some(bit) => {
+                    value = (value << 1) | (bit as u64);
+                    read += 1;
+                }
+                None => {
+                    if read == 0 {
+                        return Ok(None);
+                    }
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                           "bit stream ended in the middle of a value"));
                 }
             }
-            Ok(Some(res))
         }
+        Ok(Some(value))
     }
 }
 
-impl<R, P> Iterator for BitReader<R, P> where R: Read, P: Padding {
+impl<R, P, O> Iterator for BitReader<R, P, O> where R: Read, P: Padding, O: BitOrder {
     type Item = bool;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -356,4 +635,341 @@ mod tests {
             assert!(bit_reader.read_bit().unwrap().is_none());
         }
     }
+
+    #[test]
+    fn test_write_bits_matches_single_bit_writes() {
+        let mut single = Vec::new();
+        {
+            let mut bit_writer = BitWriter::new(&mut single);
+            for bit in &[true, true, false, true, true, false, false, true, true, true] {
+                assert!(bit_writer.write_bit(*bit).is_ok());
+            }
+        }
+
+        let mut multi = Vec::new();
+        {
+            let mut bit_writer = BitWriter::new(&mut multi);
+            assert!(bit_writer.write_bits(4, 0b1101).is_ok());
+            assert!(bit_writer.write_bits(6, 0b100111).is_ok());
+        }
+
+        assert_eq!(single, multi);
+    }
+
+    #[test]
+    fn test_write_bits_masks_value_to_bit_width() {
+        let mut vec = Vec::new();
+        {
+            let mut bit_writer = BitWriter::new(&mut vec);
+            assert!(bit_writer.write_bits(4, 0xFF0).is_ok());
+        }
+        assert_eq!(vec, vec![0]);
+    }
+
+    #[test]
+    fn test_read_bits_matches_single_bit_reads() {
+        let mut vec = Cursor::new(vec![200, 192]);
+        let mut bit_reader = BitReader::new(&mut vec);
+        assert_eq!(bit_reader.read_bits(4).unwrap().unwrap(), 0b1100);
+        assert_eq!(bit_reader.read_bits(12).unwrap().unwrap(), 0b1000_1100_0000);
+        assert!(bit_reader.read_bits(1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_bits_empty_stream() {
+        let mut vec = Cursor::new(&[]);
+        let mut bit_reader = BitReader::new(&mut vec);
+        assert!(bit_reader.read_bits(8).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_bits_errors_on_truncated_value() {
+        let mut vec = Cursor::new(vec![0xFF]);
+        let mut bit_reader = BitReader::new(&mut vec);
+        let err = bit_reader.read_bits(16).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_write_read_bits_round_trip() {
+        let mut vec = Vec::new();
+        {
+            let mut bit_writer = BitWriter::new(&mut vec);
+            assert!(bit_writer.write_bits(3, 0b101).is_ok());
+            assert!(bit_writer.write_bits(13, 0b1_0110_1100_1110).is_ok());
+        }
+
+        let mut cur = Cursor::new(&vec);
+        let mut bit_reader = BitReader::new(&mut cur);
+        assert_eq!(bit_reader.read_bits(3).unwrap().unwrap(), 0b101);
+        assert_eq!(bit_reader.read_bits(13).unwrap().unwrap(), 0b1_0110_1100_1110);
+    }
+
+    #[test]
+    fn test_lsb_matches_libflate_byte_layout() {
+        // libflate's BitWriter packs bits into a byte starting from the least significant bit,
+        // so writing 1,1,0,1,1,0,0,1 (in that order) yields the byte 0b1001_1011.
+        let mut vec = Vec::new();
+        {
+            let mut bit_writer = BitWriter::<_, _, Lsb>::with_padding_and_order(&mut vec, NoPadding::new());
+            for bit in &[true, true, false, true, true, false, false, true] {
+                assert!(bit_writer.write_bit(*bit).is_ok());
+            }
+        }
+        assert_eq!(vec, vec![0b1001_1011]);
+    }
+
+    #[test]
+    fn test_lsb_write_read_round_trip() {
+        let mut vec = Vec::new();
+        {
+            let mut bit_writer = BitWriter::<_, _, Lsb>::with_padding_and_order(&mut vec, LengthPadding::new());
+            assert!(bit_writer.write_bit(true).is_ok());
+            assert!(bit_writer.write_bits(12, 0b1010_1100_1110).is_ok());
+        }
+
+        let mut cur = Cursor::new(&vec);
+        let mut bit_reader = BitReader::<_, _, Lsb>::with_padding_and_order(&mut cur, LengthPadding::new());
+        assert_eq!(bit_reader.read_bit().unwrap().unwrap(), true);
+        assert_eq!(bit_reader.read_bits(12).unwrap().unwrap(), 0b1010_1100_1110);
+        assert!(bit_reader.read_bit().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lsb_write_bits_matches_single_bit_writes() {
+        let bits = [true, true, false, true, true, false, false, true,
+                    true, true, false, false, true, false, true, false,
+                    false, true, true, false];
+
+        let mut single = Vec::new();
+        {
+            let mut bit_writer = BitWriter::<_, _, Lsb>::with_padding_and_order(&mut single, NoPadding::new());
+            for bit in &bits {
+                assert!(bit_writer.write_bit(*bit).is_ok());
+            }
+        }
+
+        let mut multi = Vec::new();
+        {
+            let mut bit_writer = BitWriter::<_, _, Lsb>::with_padding_and_order(&mut multi, NoPadding::new());
+            assert!(bit_writer.write_bits(16, 0b1101_1001_1100_1010).is_ok());
+            assert!(bit_writer.write_bits(4, 0b0110).is_ok());
+        }
+
+        assert_eq!(single, multi);
+
+        let mut cur = Cursor::new(&multi);
+        let mut bit_reader = BitReader::<_, _, Lsb>::with_padding_and_order(&mut cur, NoPadding::new());
+        assert_eq!(bit_reader.read_bits(16).unwrap().unwrap(), 0b1101_1001_1100_1010);
+        assert_eq!(bit_reader.read_bits(4).unwrap().unwrap(), 0b0110);
+    }
+
+    #[test]
+    fn test_finish_pads_and_returns_inner() {
+        let vec = Vec::new();
+        let mut bit_writer = BitWriter::with_padding(vec, LengthPadding::new());
+        assert!(bit_writer.write_bit(true).is_ok());
+        assert!(bit_writer.write_bit(false).is_ok());
+
+        let vec = bit_writer.finish().unwrap();
+        assert_eq!(vec, vec![128, 2]);
+    }
+
+    #[test]
+    fn test_finish_empty_does_not_pad_twice_on_drop() {
+        let vec = Vec::new();
+        let bit_writer = BitWriter::with_padding(vec, LengthPadding::new());
+        let vec = bit_writer.finish().unwrap();
+        assert_eq!(vec, vec![8u8]);
+    }
+
+    #[test]
+    fn test_flush_does_not_finalize_padding() {
+        let mut vec = Vec::new();
+        {
+            let mut bit_writer = BitWriter::new(&mut vec);
+            assert!(bit_writer.write_bit(true).is_ok());
+            assert!(bit_writer.flush().is_ok());
+            assert_eq!(bit_writer.as_inner_ref().len(), 0);
+        }
+        assert_eq!(vec.len(), 1);
+    }
+
+    #[test]
+    fn test_as_inner_accessors() {
+        let vec = Vec::new();
+        let mut bit_writer = BitWriter::new(vec);
+        assert!(bit_writer.write_bit(true).is_ok());
+        assert_eq!(bit_writer.as_inner_ref().len(), 0);
+        bit_writer.as_inner_mut().push(0xFF);
+        assert_eq!(bit_writer.as_inner_ref(), &vec![0xFF]);
+    }
+
+    #[test]
+    fn test_write_read_value_round_trip() {
+        let mut vec = Vec::new();
+        {
+            let mut bit_writer = BitWriter::new(&mut vec);
+            assert!(bit_writer.write_value(&true).is_ok());
+            assert!(bit_writer.write_value(&42u8).is_ok());
+            assert!(bit_writer.write_value(&(-1i16)).is_ok());
+        }
+
+        let mut cur = Cursor::new(&vec);
+        let mut bit_reader = BitReader::new(&mut cur);
+        assert_eq!(bit_reader.read_value::<bool>().unwrap().unwrap(), true);
+        assert_eq!(bit_reader.read_value::<u8>().unwrap().unwrap(), 42u8);
+        assert_eq!(bit_reader.read_value::<i16>().unwrap().unwrap(), -1i16);
+    }
+
+    #[test]
+    fn test_write_read_value_round_trip_lsb() {
+        let mut vec = Vec::new();
+        {
+            let mut bit_writer = BitWriter::<_, _, Lsb>::with_padding_and_order(&mut vec, NoPadding::new());
+            assert!(bit_writer.write_value(&true).is_ok());
+            assert!(bit_writer.write_value(&42u8).is_ok());
+            assert!(bit_writer.write_value(&(-1i16)).is_ok());
+        }
+
+        let mut cur = Cursor::new(&vec);
+        let mut bit_reader = BitReader::<_, _, Lsb>::with_padding_and_order(&mut cur, NoPadding::new());
+        assert_eq!(bit_reader.read_value::<bool>().unwrap().unwrap(), true);
+        assert_eq!(bit_reader.read_value::<u8>().unwrap().unwrap(), 42u8);
+        assert_eq!(bit_reader.read_value::<i16>().unwrap().unwrap(), -1i16);
+    }
+
+    #[test]
+    fn test_read_value_end_of_stream() {
+        let mut vec = Cursor::new(&[]);
+        let mut bit_reader = BitReader::new(&mut vec);
+        assert!(bit_reader.read_value::<u32>().unwrap().is_none());
+    }
+
+    /// A `Write` wrapper that counts how many times `write_all` is called on it, to verify the
+    /// writer is batching complete bytes instead of writing one at a time.
+    struct CountingWriter<W> {
+        inner: W,
+        write_all_calls: usize,
+    }
+
+    impl<W: Write> Write for CountingWriter<W> {
+        fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+            self.inner.write(buf)
+        }
+
+        fn write_all(&mut self, buf: &[u8]) -> IOResult<()> {
+            self.write_all_calls += 1;
+            self.inner.write_all(buf)
+        }
+
+        fn flush(&mut self) -> IOResult<()> {
+            self.inner.flush()
+        }
+    }
+
+    /// A `Write` wrapper that accepts the first `accept_bytes` bytes handed to `write_all` and
+    /// then fails every call after that, to exercise what happens when the inner writer errors
+    /// out from under a buffered `BitWriter`.
+    struct FailingWriter {
+        accepted: Vec<u8>,
+        accept_bytes: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+            self.write_all(buf)?;
+            Ok(buf.len())
+        }
+
+        fn write_all(&mut self, buf: &[u8]) -> IOResult<()> {
+            if self.accepted.len() + buf.len() > self.accept_bytes {
+                let remaining = self.accept_bytes - self.accepted.len();
+                self.accepted.extend_from_slice(&buf[..remaining]);
+                return Err(Error::other("writer out of room"));
+            }
+            self.accepted.extend_from_slice(buf);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> IOResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_full_buffer_flush_error_does_not_panic_on_next_write() {
+        let mut writer = FailingWriter { accepted: Vec::new(), accept_bytes: 0 };
+        let mut bit_writer = BitWriter::new(&mut writer);
+        // The writer rejects every byte, so each flush triggered by a full buffer fails; ignore
+        // those errors and keep writing, exactly like a caller that only checks the return value
+        // of `finish`/`flush` at the end.
+        for _ in 0..BUFFER_SIZE {
+            let _ = bit_writer.write_bits(8, 0xAB);
+        }
+        // Without the fix, `buffer_fill` was left at `BUFFER_SIZE` by the earlier failed flush,
+        // so this next write indexes past the end of the buffer and panics instead of being
+        // accepted into the now-empty buffer.
+        assert!(bit_writer.write_bits(8, 0xAB).is_ok());
+        // The single byte just buffered still needs to go somewhere; flushing it fails since the
+        // inner writer rejects everything, but must return an `IOResult` rather than panic.
+        assert!(bit_writer.flush().is_err());
+    }
+
+    #[test]
+    fn test_finish_does_not_resend_buffer_after_failed_flush() {
+        let mut writer = FailingWriter { accepted: Vec::new(), accept_bytes: 2 };
+        let mut bit_writer = BitWriter::new(&mut writer);
+        assert!(bit_writer.write_bits(8, 0xAA).is_ok());
+        assert!(bit_writer.write_bits(8, 0xBB).is_ok());
+        assert!(bit_writer.write_bits(8, 0xCC).is_ok());
+        assert!(bit_writer.write_bits(8, 0xDD).is_ok());
+
+        // `finish` flushes the 4 buffered bytes in one `write_all` call, of which only the first
+        // 2 are accepted before the writer errors. `finish` consumes `self`, so `Drop` runs
+        // immediately afterwards; it must not resend any of the bytes already handed to the
+        // inner writer.
+        assert!(bit_writer.finish().is_err());
+        assert_eq!(writer.accepted, vec![0xAA, 0xBB]);
+    }
+
+    /// A `Read` wrapper that counts how many times `read` is called on it, to verify the reader
+    /// is refilling in bulk instead of one byte at a time.
+    struct CountingReader<R> {
+        inner: R,
+        read_calls: usize,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+            self.read_calls += 1;
+            self.inner.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_writer_batches_inner_write_all_calls() {
+        let mut writer = CountingWriter { inner: Vec::new(), write_all_calls: 0 };
+        {
+            let mut bit_writer = BitWriter::new(&mut writer);
+            for _ in 0..10_000 {
+                assert!(bit_writer.write_bits(8, 0xAB).is_ok());
+            }
+            assert!(bit_writer.flush().is_ok());
+        }
+        assert_eq!(writer.inner.len(), 10_000);
+        assert!(writer.write_all_calls < 10, "expected a handful of bulk writes, got {}", writer.write_all_calls);
+    }
+
+    #[test]
+    fn test_reader_batches_inner_read_calls() {
+        let data = vec![0xABu8; 10_000];
+        let mut reader = CountingReader { inner: Cursor::new(data), read_calls: 0 };
+        let mut bit_reader = BitReader::new(&mut reader);
+        for _ in 0..10_000 {
+            assert_eq!(bit_reader.read_bits(8).unwrap().unwrap(), 0xAB);
+        }
+        assert!(bit_reader.read_bits(1).unwrap().is_none());
+        assert!(reader.read_calls < 10, "expected a handful of bulk reads, got {}", reader.read_calls);
+    }
 }
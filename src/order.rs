@@ -0,0 +1,55 @@
+//! A module for configuring the bit order used by `BitReader`/`BitWriter`
+//!
+//! This module contains the trait for selecting whether bits are packed into a byte starting
+//! from the most significant bit, or the least significant bit.
+
+/// **BitOrder** decides which bit of a byte is addressed by a given fill count.
+///
+/// [BitReader](../struct.BitReader.html)/[BitWriter](../struct.BitWriter.html) are generic over
+/// this trait, so the bit order they pack into a byte can be swapped out to interoperate with
+/// other bit stream formats.
+pub trait BitOrder {
+    /// Get the bit mask addressing the `fill`-th bit written/read in the current byte, counting
+    /// from 0.
+    fn mask(fill: u8) -> u8;
+
+    /// Convert a byte between its physical packing for this bit order and the canonical,
+    /// most-significant-bit-first layout `write_bits`/`read_bits` use internally to batch
+    /// several bits into a single accumulator operation. Self-inverse: applying it twice
+    /// returns the original byte.
+    fn reorder(byte: u8) -> u8;
+}
+
+/// **Msb** packs bits into a byte starting from the most significant bit.
+///
+/// This is the order [BitReader](../struct.BitReader.html)/[BitWriter](../struct.BitWriter.html)
+/// have always used, and remains the default.
+#[derive(Default, Debug)]
+pub struct Msb {}
+
+impl BitOrder for Msb {
+    fn mask(fill: u8) -> u8 {
+        128u8 >> fill
+    }
+
+    fn reorder(byte: u8) -> u8 {
+        byte
+    }
+}
+
+/// **Lsb** packs bits into a byte starting from the least significant bit.
+///
+/// This matches the bit order used by DEFLATE/zlib style formats, e.g. as produced by the
+/// `libflate` crate.
+#[derive(Default, Debug)]
+pub struct Lsb {}
+
+impl BitOrder for Lsb {
+    fn mask(fill: u8) -> u8 {
+        1u8 << fill
+    }
+
+    fn reorder(byte: u8) -> u8 {
+        byte.reverse_bits()
+    }
+}
@@ -0,0 +1,80 @@
+//! A module for storing and loading typed values through a bit stream
+//!
+//! This module contains the `BitStore` trait, which lets whole values be written to and read
+//! from a [BitWriter](../struct.BitWriter.html)/[BitReader](../struct.BitReader.html) without the
+//! caller having to assemble the individual bits themselves.
+
+use std::io::{Read, Write};
+use std::io::Result as IOResult;
+
+use crate::{BitOrder, BitReader, BitWriter, Padding};
+
+/// **BitStore** lets a value be written to and read from a bit stream as a whole.
+///
+/// Implementations are provided for `bool`, `u8`/`u16`/`u32`/`u64`, and `i8`/`i16`/`i32`/`i64`
+/// (stored in two's complement), each at their full bit width.
+pub trait BitStore: Sized {
+    /// Write this value to the writer.
+    ///
+    /// # Failures
+    /// Returns an error if the inner writer returns an error
+    fn write_to<W: Write, P: Padding, O: BitOrder>(&self, w: &mut BitWriter<W, P, O>) -> IOResult<()>;
+
+    /// Read a value of this type from the reader.
+    ///
+    /// End of stream is signaled by returning `Ok(None)`, in the same way as
+    /// [read_bits](../struct.BitReader.html#method.read_bits).
+    ///
+    /// # Failures
+    /// Will return an error if the inner reader returns one, or if the stream ends in the middle
+    /// of the value
+    fn read_from<R: Read, P: Padding, O: BitOrder>(r: &mut BitReader<R, P, O>) -> IOResult<Option<Self>>;
+}
+
+impl BitStore for bool {
+    fn write_to<W: Write, P: Padding, O: BitOrder>(&self, w: &mut BitWriter<W, P, O>) -> IOResult<()> {
+        w.write_bit(*self)
+    }
+
+    fn read_from<R: Read, P: Padding, O: BitOrder>(r: &mut BitReader<R, P, O>) -> IOResult<Option<Self>> {
+        r.read_bit()
+    }
+}
+
+macro_rules! impl_bit_store_uint {
+    ($ty:ty, $width:expr) => {
+        impl BitStore for $ty {
+            fn write_to<W: Write, P: Padding, O: BitOrder>(&self, w: &mut BitWriter<W, P, O>) -> IOResult<()> {
+                w.write_bits($width, *self as u64)
+            }
+
+            fn read_from<R: Read, P: Padding, O: BitOrder>(r: &mut BitReader<R, P, O>) -> IOResult<Option<Self>> {
+                Ok(r.read_bits($width)?.map(|value| value as $ty))
+            }
+        }
+    };
+}
+
+impl_bit_store_uint!(u8, 8);
+impl_bit_store_uint!(u16, 16);
+impl_bit_store_uint!(u32, 32);
+impl_bit_store_uint!(u64, 64);
+
+macro_rules! impl_bit_store_int {
+    ($ty:ty, $uty:ty, $width:expr) => {
+        impl BitStore for $ty {
+            fn write_to<W: Write, P: Padding, O: BitOrder>(&self, w: &mut BitWriter<W, P, O>) -> IOResult<()> {
+                w.write_bits($width, *self as $uty as u64)
+            }
+
+            fn read_from<R: Read, P: Padding, O: BitOrder>(r: &mut BitReader<R, P, O>) -> IOResult<Option<Self>> {
+                Ok(r.read_bits($width)?.map(|value| value as $uty as $ty))
+            }
+        }
+    };
+}
+
+impl_bit_store_int!(i8, u8, 8);
+impl_bit_store_int!(i16, u16, 16);
+impl_bit_store_int!(i32, u32, 32);
+impl_bit_store_int!(i64, u64, 64);